@@ -0,0 +1,114 @@
+// Middleware de autenticação e autorização.
+//
+// Centraliza a decodificação do bearer token (feita uma única vez por
+// requisição) e a checagem de escopo, para que os handlers não precisem
+// repetir o bloco "extract_token_claims is none -> 401" e para permitir
+// tokens com acesso limitado (ex.: somente `users:read`).
+use crate::error::ApiError;
+use crate::{AppState, Claims, ISSUER_DELETE, ISSUER_LOGIN};
+use jsonwebtoken::{decode, Validation};
+use tide::{Middleware, Next, Request, Response};
+
+// Decodifica e valida o bearer token, exigindo que seu `iss` seja
+// `expected_issuer` — assim um token de login não pode ser aceito numa
+// rota que espera, por exemplo, um token de confirmação de exclusão.
+pub async fn extract_token_claims_for_issuer(
+    req: &Request<AppState>,
+    expected_issuer: &str,
+) -> Option<Claims> {
+    let auth_header = req.header("Authorization")?;
+    let token = auth_header.as_str().strip_prefix("Bearer ")?;
+
+    let state = req.state();
+    let mut validation = Validation::new(state.jwt_algorithm);
+    validation.set_issuer(&[expected_issuer]);
+    let token_data = decode::<Claims>(token, &state.decoding_key, &validation).ok()?;
+    let claims = token_data.claims;
+
+    // O token pode estar assinado e dentro da validade, mas o principal por
+    // trás dele pode ter sido bloqueado (ou removido) desde a emissão; por
+    // isso o estado atual é sempre conferido no banco, nunca confiado só
+    // pelas claims.
+    match state.db.get_admin(&claims.username).await {
+        Ok(Some(admin)) if !admin.blocked => Some(claims),
+        _ => None,
+    }
+}
+
+/// Atalho para o caso comum: tokens de acesso normais (issuer `login`).
+pub async fn extract_token_claims(req: &Request<AppState>) -> Option<Claims> {
+    extract_token_claims_for_issuer(req, ISSUER_LOGIN).await
+}
+
+fn unauthorized() -> tide::Result {
+    Ok(Response::from(ApiError::MissingToken))
+}
+
+fn forbidden(scope: &str) -> tide::Result {
+    Ok(Response::from(ApiError::Forbidden(format!(
+        "Missing required scope: {}",
+        scope
+    ))))
+}
+
+/// Decodifica o bearer token (se presente) uma única vez e guarda as
+/// `Claims` resultantes na extensão da requisição. Não rejeita requisições
+/// sem token ou com token inválido por si só — isso é responsabilidade de
+/// `RequireScope`, para que rotas públicas continuem acessíveis sem auth.
+pub struct AuthMiddleware;
+
+#[tide::utils::async_trait]
+impl Middleware<AppState> for AuthMiddleware {
+    async fn handle(&self, mut req: Request<AppState>, next: Next<'_, AppState>) -> tide::Result {
+        if let Some(claims) = extract_token_claims(&req).await {
+            req.set_ext(claims);
+        }
+        next.run(req).await
+    }
+}
+
+/// Mesmo papel de `AuthMiddleware`, mas exigindo um token emitido com
+/// `iss = "delete"` (ver `issue_delete_token`) em vez de um token de login
+/// comum — usada só em `DELETE /users/:id`, para que um access token de
+/// rotina nunca baste sozinho para apagar um usuário.
+pub struct DeleteAuthMiddleware;
+
+#[tide::utils::async_trait]
+impl Middleware<AppState> for DeleteAuthMiddleware {
+    async fn handle(&self, mut req: Request<AppState>, next: Next<'_, AppState>) -> tide::Result {
+        if let Some(claims) = extract_token_claims_for_issuer(&req, ISSUER_DELETE).await {
+            req.set_ext(claims);
+        }
+        next.run(req).await
+    }
+}
+
+/// Exige que a requisição já tenha `Claims` válidas (via `AuthMiddleware`)
+/// contendo o escopo informado (ou o escopo `admin`, que libera tudo).
+pub struct RequireScope {
+    scope: &'static str,
+}
+
+pub fn require_scope(scope: &'static str) -> RequireScope {
+    RequireScope { scope }
+}
+
+#[tide::utils::async_trait]
+impl Middleware<AppState> for RequireScope {
+    async fn handle(&self, req: Request<AppState>, next: Next<'_, AppState>) -> tide::Result {
+        match req.ext::<Claims>() {
+            None => unauthorized(),
+            Some(claims) => {
+                if claims
+                    .scopes
+                    .iter()
+                    .any(|s| s == self.scope || s == "admin")
+                {
+                    next.run(req).await
+                } else {
+                    forbidden(self.scope)
+                }
+            }
+        }
+    }
+}
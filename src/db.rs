@@ -0,0 +1,333 @@
+// Camada de persistência em SQLite (via `sqlx`). Substitui os antigos
+// `Arc<Mutex<HashMap<...>>>` de `users`/`admins` por um pool de conexões,
+// para que os dados sobrevivam a um restart do processo.
+use crate::{Admin, User};
+use sqlx::sqlite::{SqlitePoolOptions, SqliteRow};
+use sqlx::{Row, SqlitePool};
+
+#[derive(Clone)]
+pub struct Database {
+    pool: SqlitePool,
+}
+
+fn admin_from_row(row: &SqliteRow) -> Result<Admin, sqlx::Error> {
+    let scopes_raw: String = row.try_get("scopes")?;
+    Ok(Admin {
+        username: row.try_get("username")?,
+        password_hash: row.try_get("password_hash")?,
+        created_at: row.try_get("created_at")?,
+        scopes: scopes_raw
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        totp_secret: row.try_get("totp_secret")?,
+        pending_totp_secret: row.try_get("pending_totp_secret")?,
+        blocked: row.try_get("blocked")?,
+        disabled_at: row.try_get("disabled_at")?,
+    })
+}
+
+fn user_from_row(row: &SqliteRow) -> Result<User, sqlx::Error> {
+    Ok(User {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        email: row.try_get("email")?,
+        created_at: row.try_get("created_at")?,
+        blocked: row.try_get("blocked")?,
+        deleted: row.try_get("deleted")?,
+        disabled_at: row.try_get("disabled_at")?,
+    })
+}
+
+// Resultado de `Database::update_user`: diferencia "não existe" de "existe
+// mas está tombstoneado", já que as duas rotas que chamam `update_user`
+// precisam responder com status distintos (404 vs 409) para cada caso.
+pub enum UpdateUserOutcome {
+    NotFound,
+    AlreadyDeleted,
+    Updated(User),
+}
+
+/// Mesma ideia de `UpdateUserOutcome`, para `Database::delete_user`.
+pub enum DeleteUserOutcome {
+    NotFound,
+    AlreadyDeleted,
+    Removed,
+}
+
+impl Database {
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+
+        let db = Self { pool };
+        db.run_migrations().await?;
+        Ok(db)
+    }
+
+    async fn run_migrations(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS users (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                blocked INTEGER NOT NULL DEFAULT 0,
+                deleted INTEGER NOT NULL DEFAULT 0,
+                disabled_at TEXT
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS admins (
+                username TEXT PRIMARY KEY,
+                password_hash TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                scopes TEXT NOT NULL,
+                totp_secret TEXT,
+                pending_totp_secret TEXT,
+                blocked INTEGER NOT NULL DEFAULT 0,
+                disabled_at TEXT
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Bancos criados antes da introdução de `blocked`/`disabled_at` não
+        // têm essas colunas; adiciona-as sem perder os dados existentes.
+        self.ensure_column("users", "blocked", "blocked INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column("users", "deleted", "deleted INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column("users", "disabled_at", "disabled_at TEXT")
+            .await?;
+        self.ensure_column("admins", "blocked", "blocked INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column("admins", "disabled_at", "disabled_at TEXT")
+            .await?;
+
+        Ok(())
+    }
+
+    // Adiciona `column` à `table` via `ALTER TABLE ... ADD COLUMN` somente se
+    // ainda não existir, já que o SQLite não suporta `ADD COLUMN IF NOT
+    // EXISTS`. `ddl` é a definição completa da coluna (nome + tipo/default).
+    async fn ensure_column(&self, table: &str, column: &str, ddl: &str) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+            .fetch_all(&self.pool)
+            .await?;
+        let exists = rows.iter().any(|row| {
+            row.try_get::<String, _>("name")
+                .map(|name| name == column)
+                .unwrap_or(false)
+        });
+        if !exists {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {}", table, ddl))
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn admin_count(&self) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM admins")
+            .fetch_one(&self.pool)
+            .await?;
+        row.try_get("count")
+    }
+
+    pub async fn insert_admin(&self, admin: &Admin) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO admins (username, password_hash, created_at, scopes, totp_secret, pending_totp_secret, blocked, disabled_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&admin.username)
+        .bind(&admin.password_hash)
+        .bind(&admin.created_at)
+        .bind(admin.scopes.join(","))
+        .bind(&admin.totp_secret)
+        .bind(&admin.pending_totp_secret)
+        .bind(admin.blocked)
+        .bind(&admin.disabled_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_admin(&self, username: &str) -> Result<Option<Admin>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM admins WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(admin_from_row).transpose()
+    }
+
+    pub async fn update_admin(&self, admin: &Admin) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE admins SET password_hash = ?, scopes = ?, totp_secret = ?, pending_totp_secret = ?,
+             blocked = ?, disabled_at = ?
+             WHERE username = ?",
+        )
+        .bind(&admin.password_hash)
+        .bind(admin.scopes.join(","))
+        .bind(&admin.totp_secret)
+        .bind(&admin.pending_totp_secret)
+        .bind(admin.blocked)
+        .bind(&admin.disabled_at)
+        .bind(&admin.username)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Atualiza o flag `blocked` (e `disabled_at`) de um admin. Usado por
+    // `/admin/admins/:username/block` e `/unblock`; é o que torna efetivas
+    // as checagens de `login` e `extract_token_claims_for_issuer`, que já
+    // liam `Admin.blocked` mas, antes deste endpoint, não tinham como vê-lo
+    // em `true`.
+    pub async fn set_admin_blocked(
+        &self,
+        username: &str,
+        blocked: bool,
+        disabled_at: Option<String>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE admins SET blocked = ?, disabled_at = ? WHERE username = ?")
+            .bind(blocked)
+            .bind(disabled_at)
+            .bind(username)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn insert_user(&self, user: &User) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO users (id, name, email, created_at, blocked, deleted, disabled_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&user.id)
+        .bind(&user.name)
+        .bind(&user.email)
+        .bind(&user.created_at)
+        .bind(user.blocked)
+        .bind(user.deleted)
+        .bind(&user.disabled_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Lista usuários não bloqueados/tombstoneados (ver `set_user_blocked` e
+    // `set_user_deleted`); continuam recuperáveis por `get_user`, para fins
+    // de auditoria.
+    pub async fn list_users(&self, limit: i64, offset: i64) -> Result<Vec<User>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM users WHERE blocked = 0 AND deleted = 0 ORDER BY created_at LIMIT ? OFFSET ?",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(user_from_row).collect()
+    }
+
+    pub async fn get_user(&self, id: &str) -> Result<Option<User>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(user_from_row).transpose()
+    }
+
+    // Um usuário tombstoneado (`deleted`) não pode ser alterado nem apagado
+    // fisicamente: ambas as operações violariam o propósito do soft-delete
+    // de preservar o registro para auditoria/recuperação. `blocked`, por ser
+    // uma suspensão reversível (e não um tombstone), não impõe essa
+    // restrição — continua podendo ser editado ou, via a rota legada, apagado.
+    pub async fn update_user(
+        &self,
+        id: &str,
+        name: Option<String>,
+        email: Option<String>,
+    ) -> Result<UpdateUserOutcome, sqlx::Error> {
+        let Some(mut user) = self.get_user(id).await? else {
+            return Ok(UpdateUserOutcome::NotFound);
+        };
+        if user.deleted {
+            return Ok(UpdateUserOutcome::AlreadyDeleted);
+        }
+
+        if let Some(name) = name {
+            user.name = name;
+        }
+        if let Some(email) = email {
+            user.email = email;
+        }
+
+        sqlx::query("UPDATE users SET name = ?, email = ? WHERE id = ?")
+            .bind(&user.name)
+            .bind(&user.email)
+            .bind(&user.id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(UpdateUserOutcome::Updated(user))
+    }
+
+    pub async fn delete_user(&self, id: &str) -> Result<DeleteUserOutcome, sqlx::Error> {
+        let Some(user) = self.get_user(id).await? else {
+            return Ok(DeleteUserOutcome::NotFound);
+        };
+        if user.deleted {
+            return Ok(DeleteUserOutcome::AlreadyDeleted);
+        }
+
+        sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(DeleteUserOutcome::Removed)
+    }
+
+    // Atualiza o flag `blocked` (e o timestamp `disabled_at`) de um usuário.
+    // Usado por `/admin/users/:id/block` e `/unblock`; reversível, ao
+    // contrário de `set_user_deleted`.
+    pub async fn set_user_blocked(
+        &self,
+        id: &str,
+        blocked: bool,
+        disabled_at: Option<String>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE users SET blocked = ?, disabled_at = ? WHERE id = ?")
+            .bind(blocked)
+            .bind(disabled_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Tombstona (ou, em tese, reverte o tombstone de) um usuário para o
+    // soft-delete de `DELETE /admin/users/:id`. Propositalmente um flag
+    // separado de `blocked`: não há endpoint que desfaça isso, ao contrário
+    // de `unblock`, que só reverte `blocked`.
+    pub async fn set_user_deleted(
+        &self,
+        id: &str,
+        deleted: bool,
+        disabled_at: Option<String>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE users SET deleted = ?, disabled_at = ? WHERE id = ?")
+            .bind(deleted)
+            .bind(disabled_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
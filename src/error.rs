@@ -0,0 +1,139 @@
+// Erro tipado único para a API. Centraliza em um só lugar a decisão de
+// status HTTP e formato do corpo (`{"status", "message"}`, mais um campo
+// `errors` para falhas de validação), no lugar dos antigos literais
+// `r#"{"error": "..."}"#` espalhados pelos handlers e dos `.unwrap()` em
+// bcrypt/serde que entravam em pânico em vez de responder com erro.
+use crate::validation::FieldError;
+use serde::Serialize;
+use tide::{Response, StatusCode};
+
+#[derive(Debug)]
+pub enum ApiError {
+    MissingCredentials,
+    InvalidCredentials,
+    MissingToken,
+    InvalidToken,
+    // 403: autenticado, mas sem permissão (escopo faltando, conta bloqueada).
+    // Carrega a mensagem porque o motivo varia por chamador.
+    Forbidden(String),
+    NotFound,
+    // 409: o recurso existe, mas o estado atual dele impede a operação
+    // (ex.: um usuário tombstoneado não pode ser editado/apagado de novo).
+    Conflict(String),
+    Validation(Vec<FieldError>),
+    // Erro 4xx repassado pelo próprio Tide (ex.: `body_json`/`query` contra
+    // um payload malformado) — culpa do chamador, então preserva o status
+    // original, mas nunca o texto bruto do erro (ver `From<tide::Error>`).
+    BadRequest(StatusCode),
+    // Falha não esperada do nosso lado (banco, serialização, Tide 5xx). A
+    // mensagem nunca é exposta ao chamador; o detalhe vai só para o log do
+    // processo (ver os `From` abaixo).
+    Internal,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    status: u16,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ValidationErrorBody {
+    status: u16,
+    message: String,
+    errors: Vec<FieldError>,
+}
+
+impl ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::MissingCredentials => StatusCode::BadRequest,
+            ApiError::InvalidCredentials => StatusCode::Unauthorized,
+            ApiError::MissingToken => StatusCode::Unauthorized,
+            ApiError::InvalidToken => StatusCode::Unauthorized,
+            ApiError::Forbidden(_) => StatusCode::Forbidden,
+            ApiError::NotFound => StatusCode::NotFound,
+            ApiError::Conflict(_) => StatusCode::Conflict,
+            ApiError::Validation(_) => StatusCode::UnprocessableEntity,
+            ApiError::BadRequest(status) => *status,
+            ApiError::Internal => StatusCode::InternalServerError,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            ApiError::MissingCredentials => "Missing username or password".to_string(),
+            ApiError::InvalidCredentials => "Invalid credentials".to_string(),
+            ApiError::MissingToken => "Authentication required for this operation".to_string(),
+            ApiError::InvalidToken => "Invalid or expired token".to_string(),
+            ApiError::Forbidden(message) => message.clone(),
+            ApiError::NotFound => "Resource not found".to_string(),
+            ApiError::Conflict(message) => message.clone(),
+            ApiError::Validation(_) => "Validation failed".to_string(),
+            ApiError::BadRequest(_) => "Malformed request".to_string(),
+            ApiError::Internal => "Internal server error".to_string(),
+        }
+    }
+}
+
+// Converte o erro tipado na resposta Tide real, com corpo JSON uniforme.
+// É a "borda" onde `ApiError` vira `Response`; os handlers só lidam com
+// `ApiError` e nunca montam o JSON de erro manualmente.
+impl From<ApiError> for Response {
+    fn from(err: ApiError) -> Response {
+        let status = err.status_code();
+        let message = err.message();
+        let body = match err {
+            ApiError::Validation(errors) => serde_json::to_string(&ValidationErrorBody {
+                status: status as u16,
+                message,
+                errors,
+            }),
+            _ => serde_json::to_string(&ErrorBody {
+                status: status as u16,
+                message,
+            }),
+        }
+        .unwrap_or_else(|_| r#"{"status":500,"message":"Failed to serialize error"}"#.to_string());
+
+        let mut response = Response::new(status);
+        response.set_body(body);
+        response.set_content_type("application/json");
+        response
+    }
+}
+
+// Falhas do banco e de serialização não são esperadas pelo chamador; o
+// detalhe vai pro stderr do processo (não tem outro logger no crate), nunca
+// no corpo da resposta.
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        eprintln!("database error: {}", err);
+        ApiError::Internal
+    }
+}
+
+// `tide::Error` cobre tanto falhas do próprio framework (ex.: handler de
+// outra rota retornando 500) quanto rejeições de parsing do pedido do
+// chamador (`body_json`, `query`, `param` com payload malformado), que o
+// Tide já marca com um status 4xx. Preserva esse status — senão um JSON
+// malformado vira, erradamente, um 500 — mas nunca o texto do erro, que
+// pode conter detalhes do payload ou da implementação.
+impl From<tide::Error> for ApiError {
+    fn from(err: tide::Error) -> Self {
+        let status = err.status();
+        if (400..500).contains(&(status as u16)) {
+            ApiError::BadRequest(status)
+        } else {
+            eprintln!("internal error: {}", err);
+            ApiError::Internal
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(err: serde_json::Error) -> Self {
+        eprintln!("serialization error: {}", err);
+        ApiError::Internal
+    }
+}
@@ -1,10 +1,50 @@
 use async_std::sync::{Arc, Mutex};
 use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use tide::{Request, Response, Result, StatusCode};
+use std::future::Future;
+use std::pin::Pin;
+use tide::{Request, Response, StatusCode};
+
+mod auth;
+mod db;
+mod error;
+mod totp;
+mod validation;
+use auth::{extract_token_claims, require_scope, AuthMiddleware, DeleteAuthMiddleware};
+use db::{Database, DeleteUserOutcome, UpdateUserOutcome};
+use error::ApiError;
+use validation::{estimate_password_strength, validate_email, FieldError};
+
+// Resultado de um handler de rota: os handlers nunca montam JSON de erro na
+// mão, só retornam a variante de `ApiError` apropriada, que é convertida em
+// `Response` na borda (ver `adapt`) ou via `Response::from`.
+type ApiResult = std::result::Result<Response, ApiError>;
+
+// Adapta um handler `Fn(Request<AppState>) -> Future<Output = ApiResult>`
+// para o tipo exigido por `tide::Route::{get,post,put,delete}`
+// (`Future<Output = tide::Result<Response>>`), já que o trait `Endpoint` do
+// Tide fixa o tipo de erro em `tide::Error`. Fazer essa conversão aqui, uma
+// única vez, é o que permite que cada handler use `ApiError` diretamente.
+fn adapt<F, Fut>(
+    handler: F,
+) -> impl Fn(Request<AppState>) -> Pin<Box<dyn Future<Output = tide::Result<Response>> + Send>>
+       + Send
+       + Sync
+       + 'static
+where
+    F: Fn(Request<AppState>) -> Fut + Send + Sync + Clone + 'static,
+    Fut: Future<Output = ApiResult> + Send + 'static,
+{
+    move |req: Request<AppState>| {
+        let handler = handler.clone();
+        Box::pin(async move { Ok(handler(req).await.unwrap_or_else(Response::from)) })
+    }
+}
 
 // Estruturas de dados
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +53,15 @@ pub struct User {
     pub name: String,
     pub email: String,
     pub created_at: String,
+    // Bloqueio administrativo reversível (`/admin/users/:id/block` e
+    // `/unblock`). Também esconde o registro de `get_all_users`.
+    pub blocked: bool,
+    // Tombstone permanente deixado por `DELETE /admin/users/:id`: mesmo
+    // efeito de ocultação que `blocked`, mas não existe endpoint para
+    // desfazê-lo (ao contrário de `blocked`, que `unblock` reverte), para
+    // não confundir "conta suspensa" com "registro apagado".
+    pub deleted: bool,
+    pub disabled_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,152 +76,536 @@ pub struct UpdateUserRequest {
     pub email: Option<String>,
 }
 
+// Escopos reconhecidos pelo `RequireScope`. `SCOPE_ADMIN` libera qualquer
+// operação. `users:read` ainda não é exigido por nenhuma rota (GET /users
+// continua público) mas já é um escopo válido para tokens restritos.
+pub const SCOPE_USERS_WRITE: &str = "users:write";
+pub const SCOPE_USERS_DELETE: &str = "users:delete";
+pub const SCOPE_ADMIN: &str = "admin";
+
 // Estruturas para autenticação
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Admin {
     pub username: String,
     pub password_hash: String,
     pub created_at: String,
+    pub scopes: Vec<String>,
+    // Segredo TOTP ativo (base32). `None` significa 2FA desligado.
+    pub totp_secret: Option<String>,
+    // Segredo gerado por `/auth/2fa/enroll` aguardando confirmação via
+    // `/auth/2fa/confirm` antes de passar a ser exigido no login.
+    pub pending_totp_secret: Option<String>,
+    // Conta bloqueada: `login` recusa com 403 mesmo com credenciais
+    // corretas, e `extract_token_claims` rejeita qualquer token já emitido
+    // para este usuário (ver `auth::extract_token_claims_for_issuer`).
+    pub blocked: bool,
+    pub disabled_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
+    pub totp_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnrollTotpResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub totp_code: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub token: String,
     pub expires_in: String,
+    pub refresh_token: String,
+    pub refresh_expires_in: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteTokenResponse {
+    pub token: String,
+    pub expires_in: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub username: String,
     pub exp: usize,
+    pub scopes: Vec<String>,
+    pub iss: String,
+}
+
+// Issuers suportados. Cada um autoriza o token apenas para sua própria
+// finalidade: um token de login não pode ser reaproveitado em
+// `DELETE /users/:id`, que exige um token `delete` obtido via
+// `POST /auth/delete-token` (ver `issue_delete_token`).
+pub const ISSUER_LOGIN: &str = "login";
+pub const ISSUER_DELETE: &str = "delete";
+
+// Token de atualização (opaco, não-JWT) usado para renovar o access token
+// sem exigir novo login. Apenas o hash SHA-256 do valor bruto é persistido.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub username: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+}
+
+const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+// Bem mais curto que o access token: só precisa sobreviver ao tempo entre
+// pedir o token de exclusão e confirmar a ação em `DELETE /users/:id`.
+const DELETE_TOKEN_TTL_MINUTES: i64 = 5;
+
+// Gera um token opaco de 32 bytes aleatórios, codificado em hex.
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex_encode(&bytes)
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hex_encode(&hasher.finalize())
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Remove do mapa as entradas já expiradas, para que o registro de refresh
+// tokens não cresça sem limite ao longo da vida do processo (toda rotação
+// ou logout deixava o registro antigo/revogado para trás, sem nunca
+// removê-lo). Entradas revogadas mas ainda dentro do TTL são mantidas de
+// propósito: é o que permite `refresh_token` detectar o reuso de um token
+// já consumido e revogar a cadeia inteira.
+fn prune_expired_refresh_tokens(tokens: &mut HashMap<String, RefreshToken>) {
+    let now = Utc::now();
+    tokens.retain(|_, record| record.expires_at > now);
 }
 
 // Estado da aplicação
-type UserDatabase = Arc<Mutex<HashMap<String, User>>>;
-type AdminDatabase = Arc<Mutex<HashMap<String, Admin>>>;
+type RefreshTokenDatabase = Arc<Mutex<HashMap<String, RefreshToken>>>;
+
+const DEFAULT_USERS_PAGE_LIMIT: i64 = 50;
+const MAX_USERS_PAGE_LIMIT: i64 = 200;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub users: UserDatabase,
-    pub admins: AdminDatabase,
-    pub jwt_secret: String,
+    pub db: Database,
+    pub refresh_tokens: RefreshTokenDatabase,
+    pub encoding_key: EncodingKey,
+    pub decoding_key: DecodingKey,
+    pub jwt_algorithm: Algorithm,
 }
 
-impl AppState {
-    fn new() -> Self {
-        let mut admins = HashMap::new();
-        
-        // Criar admin padrão (usuário: admin, senha: admin123)
-        let password_hash = hash("admin123", DEFAULT_COST).unwrap();
-        let admin = Admin {
-            username: "admin".to_string(),
-            password_hash,
-            created_at: Utc::now().to_rfc3339(),
-        };
-        admins.insert("admin".to_string(), admin);
+// Carrega o par de chaves RSA apontado por `JWT_PRIVATE_KEY`/`JWT_PUBLIC_KEY`
+// (caminhos para arquivos PEM) e assina com RS256. Se as variáveis não
+// estiverem definidas ou as chaves não puderem ser lidas, cai de volta para
+// HS256 com `JWT_SECRET`, mantendo compatibilidade com deployments antigos.
+fn load_signing_keys() -> (EncodingKey, DecodingKey, Algorithm) {
+    let keys = std::env::var("JWT_PRIVATE_KEY").ok().zip(std::env::var("JWT_PUBLIC_KEY").ok());
 
-        Self {
-            users: Arc::new(Mutex::new(HashMap::new())),
-            admins: Arc::new(Mutex::new(admins)),
-            jwt_secret: std::env::var("JWT_SECRET").unwrap_or_else(|_| "your-secret-key-change-in-production".to_string()),
+    if let Some((private_key_path, public_key_path)) = keys {
+        let loaded = std::fs::read(&private_key_path).ok().zip(std::fs::read(&public_key_path).ok());
+        if let Some((private_pem, public_pem)) = loaded {
+            let parsed = EncodingKey::from_rsa_pem(&private_pem)
+                .ok()
+                .zip(DecodingKey::from_rsa_pem(&public_pem).ok());
+            if let Some((encoding_key, decoding_key)) = parsed {
+                return (encoding_key, decoding_key, Algorithm::RS256);
+            }
         }
     }
+
+    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "your-secret-key-change-in-production".to_string());
+    (
+        EncodingKey::from_secret(secret.as_ref()),
+        DecodingKey::from_secret(secret.as_ref()),
+        Algorithm::HS256,
+    )
 }
 
-// Middleware de autenticação
-async fn extract_token_claims(req: &Request<AppState>) -> Option<Claims> {
-    let auth_header = req.header("Authorization")?;
-    let token = auth_header.as_str().strip_prefix("Bearer ")?;
-    
-    let jwt_secret = &req.state().jwt_secret;
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &Validation::new(Algorithm::HS256),
-    ).ok()?;
-    
-    Some(token_data.claims)
+impl AppState {
+    async fn new() -> Result<Self, sqlx::Error> {
+        let database_url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "sqlite://rust_api_crud.db?mode=rwc".to_string());
+        let db = Database::connect(&database_url).await?;
+
+        // Semear o admin padrão (usuário: admin, senha: admin123) apenas se a
+        // tabela estiver vazia, para não recriá-lo a cada restart.
+        if db.admin_count().await? == 0 {
+            let password_hash = hash("admin123", DEFAULT_COST).unwrap();
+            let admin = Admin {
+                username: "admin".to_string(),
+                password_hash,
+                created_at: Utc::now().to_rfc3339(),
+                scopes: vec![SCOPE_ADMIN.to_string()],
+                totp_secret: None,
+                pending_totp_secret: None,
+                blocked: false,
+                disabled_at: None,
+            };
+            db.insert_admin(&admin).await?;
+        }
+
+        let (encoding_key, decoding_key, jwt_algorithm) = load_signing_keys();
+
+        Ok(Self {
+            db,
+            refresh_tokens: Arc::new(Mutex::new(HashMap::new())),
+            encoding_key,
+            decoding_key,
+            jwt_algorithm,
+        })
+    }
+
+    // Emite um token JWT assinado para o issuer e TTL informados.
+    fn mint_issuer_token(
+        &self,
+        username: &str,
+        scopes: Vec<String>,
+        issuer: &str,
+        ttl: Duration,
+    ) -> tide::Result<(String, DateTime<Utc>)> {
+        let expiration = Utc::now() + ttl;
+        let claims = Claims {
+            username: username.to_string(),
+            exp: expiration.timestamp() as usize,
+            scopes,
+            iss: issuer.to_string(),
+        };
+        let header = Header::new(self.jwt_algorithm);
+        let token = encode(&header, &claims, &self.encoding_key)
+            .map_err(|_| tide::Error::from_str(500, "Failed to generate token"))?;
+        Ok((token, expiration))
+    }
+
+    // Emite um novo par access/refresh token (issuer "login") para o usuário
+    // informado e persiste o registro do refresh token (apenas o hash).
+    async fn issue_tokens(
+        &self,
+        username: &str,
+        scopes: Vec<String>,
+    ) -> tide::Result<(String, DateTime<Utc>, String, DateTime<Utc>)> {
+        let (access_token, access_expiration) = self.mint_issuer_token(
+            username,
+            scopes,
+            ISSUER_LOGIN,
+            Duration::minutes(ACCESS_TOKEN_TTL_MINUTES),
+        )?;
+
+        let refresh_token = generate_refresh_token();
+        let issued_at = Utc::now();
+        let refresh_expiration = issued_at + Duration::days(REFRESH_TOKEN_TTL_DAYS);
+        let record = RefreshToken {
+            token_hash: hash_refresh_token(&refresh_token),
+            username: username.to_string(),
+            issued_at,
+            expires_at: refresh_expiration,
+            revoked: false,
+        };
+        let mut refresh_tokens = self.refresh_tokens.lock().await;
+        prune_expired_refresh_tokens(&mut refresh_tokens);
+        refresh_tokens.insert(record.token_hash.clone(), record);
+
+        Ok((access_token, access_expiration, refresh_token, refresh_expiration))
+    }
 }
 
 // ROTAS DE AUTENTICAÇÃO
 
 // POST /auth/login - Fazer login e receber token
-async fn login(mut req: Request<AppState>) -> Result {
+async fn login(mut req: Request<AppState>) -> ApiResult {
     let login_request: LoginRequest = req.body_json().await?;
-    let admins = req.state().admins.lock().await;
-    
-    if let Some(admin) = admins.get(&login_request.username) {
-        if verify(&login_request.password, &admin.password_hash).unwrap_or(false) {
-            // Gerar JWT token
-            let expiration = Utc::now() + Duration::hours(24);
-            let claims = Claims {
-                username: admin.username.clone(),
-                exp: expiration.timestamp() as usize,
-            };
-            
-            let jwt_secret = &req.state().jwt_secret;
-            let token = encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(jwt_secret.as_ref()),
-            ).map_err(|_| tide::Error::from_str(500, "Failed to generate token"))?;
-            
-            let response_data = LoginResponse {
-                token,
-                expires_in: expiration.to_rfc3339(),
-            };
-            
-            let mut response = Response::new(StatusCode::Ok);
-            response.set_body(serde_json::to_string(&response_data)?);
-            response.set_content_type("application/json");
-            return Ok(response);
+    let admin = req
+        .state()
+        .db
+        .get_admin(&login_request.username)
+        .await?
+        .ok_or(ApiError::InvalidCredentials)?;
+
+    if admin.blocked {
+        return Err(ApiError::Forbidden("Account blocked".to_string()));
+    }
+
+    if !verify(&login_request.password, &admin.password_hash).unwrap_or(false) {
+        return Err(ApiError::InvalidCredentials);
+    }
+
+    if let Some(totp_secret) = &admin.totp_secret {
+        let valid = match &login_request.totp_code {
+            Some(code) => totp::verify_code(totp_secret, code, Utc::now().timestamp() as u64),
+            None => false,
+        };
+        if !valid {
+            return Err(ApiError::InvalidCredentials);
         }
     }
-    
-    let mut response = Response::new(StatusCode::Unauthorized);
-    response.set_body(r#"{"error": "Invalid credentials"}"#);
+
+    let username = admin.username.clone();
+    let scopes = admin.scopes.clone();
+
+    let (token, expiration, refresh_token, refresh_expiration) =
+        req.state().issue_tokens(&username, scopes).await?;
+
+    let response_data = LoginResponse {
+        token,
+        expires_in: expiration.to_rfc3339(),
+        refresh_token,
+        refresh_expires_in: refresh_expiration.to_rfc3339(),
+    };
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&response_data)?);
     response.set_content_type("application/json");
     Ok(response)
 }
 
-// GET /auth/verify - Verificar se token é válido
-async fn verify_token(req: Request<AppState>) -> Result {
-    match extract_token_claims(&req).await {
-        Some(claims) => {
-            let mut response = Response::new(StatusCode::Ok);
-            response.set_body(serde_json::to_string(&claims)?);
-            response.set_content_type("application/json");
-            Ok(response)
-        }
-        None => {
-            let mut response = Response::new(StatusCode::Unauthorized);
-            response.set_body(r#"{"error": "Invalid or expired token"}"#);
-            response.set_content_type("application/json");
-            Ok(response)
+// POST /auth/refresh - Trocar um refresh token válido por um novo par de tokens
+async fn refresh_token(mut req: Request<AppState>) -> ApiResult {
+    let refresh_request: RefreshRequest = req.body_json().await?;
+    let presented_hash = hash_refresh_token(&refresh_request.refresh_token);
+
+    let mut refresh_tokens = req.state().refresh_tokens.lock().await;
+
+    let record = refresh_tokens
+        .get(&presented_hash)
+        .cloned()
+        .ok_or(ApiError::InvalidToken)?;
+
+    if record.revoked {
+        // Token já usado ou revogado sendo reapresentado: provável roubo de
+        // token. Revoga toda a cadeia do usuário como medida de contenção.
+        for token in refresh_tokens.values_mut() {
+            if token.username == record.username {
+                token.revoked = true;
+            }
         }
+        return Err(ApiError::InvalidToken);
+    }
+
+    if record.expires_at < Utc::now() {
+        return Err(ApiError::InvalidToken);
     }
+
+    // Rotação: o token apresentado é consumido e substituído por um novo par.
+    if let Some(token) = refresh_tokens.get_mut(&presented_hash) {
+        token.revoked = true;
+    }
+    drop(refresh_tokens);
+
+    let scopes = match req.state().db.get_admin(&record.username).await? {
+        Some(admin) => admin.scopes,
+        None => Vec::new(),
+    };
+    let (token, expiration, new_refresh_token, refresh_expiration) =
+        req.state().issue_tokens(&record.username, scopes).await?;
+
+    let response_data = LoginResponse {
+        token,
+        expires_in: expiration.to_rfc3339(),
+        refresh_token: new_refresh_token,
+        refresh_expires_in: refresh_expiration.to_rfc3339(),
+    };
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&response_data)?);
+    response.set_content_type("application/json");
+    Ok(response)
 }
 
-// ROTAS PROTEGIDAS (necessitam autenticação)
+// POST /auth/logout - Revogar um refresh token
+async fn logout(mut req: Request<AppState>) -> ApiResult {
+    let logout_request: LogoutRequest = req.body_json().await?;
+    let presented_hash = hash_refresh_token(&logout_request.refresh_token);
 
-// CREATE - POST /users (PROTEGIDA)
-async fn create_user(mut req: Request<AppState>) -> Result {
-    // Verificar autenticação
-    if extract_token_claims(&req).await.is_none() {
-        let mut response = Response::new(StatusCode::Unauthorized);
-        response.set_body(r#"{"error": "Authentication required for this operation"}"#);
-        response.set_content_type("application/json");
-        return Ok(response);
+    let mut refresh_tokens = req.state().refresh_tokens.lock().await;
+    let record = refresh_tokens
+        .get_mut(&presented_hash)
+        .ok_or(ApiError::InvalidToken)?;
+    record.revoked = true;
+    prune_expired_refresh_tokens(&mut refresh_tokens);
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "Logged out successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// POST /auth/2fa/enroll - Gerar um segredo TOTP pendente de confirmação (PROTEGIDA)
+async fn enroll_totp(req: Request<AppState>) -> ApiResult {
+    let claims = req.ext::<Claims>().cloned().ok_or(ApiError::MissingToken)?;
+
+    let secret = totp::generate_secret();
+    let otpauth_uri = totp::provisioning_uri(&secret, &claims.username, "rust_api_crud");
+
+    if let Some(mut admin) = req.state().db.get_admin(&claims.username).await? {
+        admin.pending_totp_secret = Some(secret.clone());
+        req.state().db.update_admin(&admin).await?;
     }
-    
+
+    let response_data = EnrollTotpResponse { secret, otpauth_uri };
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&response_data)?);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// POST /auth/2fa/confirm - Confirmar o segredo pendente e ativar o 2FA (PROTEGIDA)
+async fn confirm_totp(mut req: Request<AppState>) -> ApiResult {
+    let claims = req.ext::<Claims>().cloned().ok_or(ApiError::MissingToken)?;
+    let confirm_request: ConfirmTotpRequest = req.body_json().await?;
+
+    let mut admin = req
+        .state()
+        .db
+        .get_admin(&claims.username)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let pending_secret = admin.pending_totp_secret.clone().ok_or_else(|| {
+        ApiError::Validation(vec![FieldError {
+            field: "totp_code".to_string(),
+            message: "No pending TOTP enrollment".to_string(),
+        }])
+    })?;
+
+    if !totp::verify_code(&pending_secret, &confirm_request.totp_code, Utc::now().timestamp() as u64) {
+        return Err(ApiError::InvalidToken);
+    }
+
+    admin.totp_secret = Some(pending_secret);
+    admin.pending_totp_secret = None;
+    req.state().db.update_admin(&admin).await?;
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "Two-factor authentication enabled"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// Pontuação mínima (0-4, escala do estimador estilo zxcvbn) exigida para
+// novas senhas de admin. Configurável via `MIN_PASSWORD_SCORE`.
+fn min_password_score() -> u8 {
+    std::env::var("MIN_PASSWORD_SCORE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+// POST /auth/change-password - Trocar a própria senha de admin (PROTEGIDA)
+async fn change_password(mut req: Request<AppState>) -> ApiResult {
+    let claims = req.ext::<Claims>().cloned().ok_or(ApiError::MissingToken)?;
+    let change_request: ChangePasswordRequest = req.body_json().await?;
+
+    let mut admin = req
+        .state()
+        .db
+        .get_admin(&claims.username)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    if !verify(&change_request.current_password, &admin.password_hash).unwrap_or(false) {
+        return Err(ApiError::InvalidCredentials);
+    }
+
+    let strength = estimate_password_strength(&change_request.new_password);
+    if strength.score < min_password_score() {
+        return Err(ApiError::Validation(vec![FieldError {
+            field: "new_password".to_string(),
+            message: format!(
+                "Password too weak (score {}/4): {}",
+                strength.score,
+                strength.feedback.join(" ")
+            ),
+        }]));
+    }
+
+    admin.password_hash = hash(&change_request.new_password, DEFAULT_COST).map_err(|err| {
+        eprintln!("bcrypt error: {}", err);
+        ApiError::Internal
+    })?;
+    req.state().db.update_admin(&admin).await?;
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "Password updated successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// GET /auth/verify - Verificar se token é válido
+async fn verify_token(req: Request<AppState>) -> ApiResult {
+    let claims = extract_token_claims(&req).await.ok_or(ApiError::InvalidToken)?;
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&claims)?);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// POST /auth/delete-token - Trocar um token de login válido (com escopo
+// users:delete) por um token de curta duração (issuer "delete"), exigido
+// por `DELETE /users/:id` para que um access token de rotina nunca baste,
+// sozinho, para apagar um usuário.
+async fn issue_delete_token(req: Request<AppState>) -> ApiResult {
+    let claims = req.ext::<Claims>().cloned().ok_or(ApiError::MissingToken)?;
+    let username = claims.username.clone();
+
+    let (token, expiration) = req.state().mint_issuer_token(
+        &username,
+        claims.scopes,
+        ISSUER_DELETE,
+        Duration::minutes(DELETE_TOKEN_TTL_MINUTES),
+    )?;
+
+    let response_data = DeleteTokenResponse {
+        token,
+        expires_in: expiration.to_rfc3339(),
+    };
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&response_data)?);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// ROTAS PROTEGIDAS (necessitam autenticação)
+
+// CREATE - POST /users (PROTEGIDA, requer escopo users:write)
+async fn create_user(mut req: Request<AppState>) -> ApiResult {
     let create_request: CreateUserRequest = req.body_json().await?;
-    
+
+    if let Err(field_error) = validate_email("email", &create_request.email) {
+        return Err(ApiError::Validation(vec![field_error]));
+    }
+
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
@@ -184,92 +617,192 @@ async fn create_user(mut req: Request<AppState>) -> Result {
         name: create_request.name,
         email: create_request.email,
         created_at: Utc::now().to_rfc3339(),
+        blocked: false,
+        deleted: false,
+        disabled_at: None,
     };
-    
-    let mut users = req.state().users.lock().await;
-    users.insert(id.clone(), user.clone());
-    
+
+    req.state().db.insert_user(&user).await?;
+
     let mut response = Response::new(StatusCode::Created);
     response.set_body(serde_json::to_string(&user)?);
     response.set_content_type("application/json");
     Ok(response)
 }
 
-// UPDATE - PUT /users/:id (PROTEGIDA)
-async fn update_user(mut req: Request<AppState>) -> Result {
-    // Verificar autenticação
-    if extract_token_claims(&req).await.is_none() {
-        let mut response = Response::new(StatusCode::Unauthorized);
-        response.set_body(r#"{"error": "Authentication required for this operation"}"#);
-        response.set_content_type("application/json");
-        return Ok(response);
-    }
-    
+// UPDATE - PUT /users/:id (PROTEGIDA, requer escopo users:write)
+async fn update_user(mut req: Request<AppState>) -> ApiResult {
     let user_id: String = req.param("id")?.to_string();
     let update_request: UpdateUserRequest = req.body_json().await?;
-    
-    let mut users = req.state().users.lock().await;
-    
-    match users.get_mut(&user_id) {
-        Some(user) => {
-            if let Some(name) = update_request.name {
-                user.name = name;
-            }
-            if let Some(email) = update_request.email {
-                user.email = email;
-            }
-            
-            let mut response = Response::new(StatusCode::Ok);
-            response.set_body(serde_json::to_string(user)?);
-            response.set_content_type("application/json");
-            Ok(response)
+
+    if let Some(email) = &update_request.email {
+        if let Err(field_error) = validate_email("email", email) {
+            return Err(ApiError::Validation(vec![field_error]));
         }
-        None => {
-            let mut response = Response::new(StatusCode::NotFound);
-            response.set_body(r#"{"error": "User not found"}"#);
-            response.set_content_type("application/json");
-            Ok(response)
+    }
+
+    let user = match req
+        .state()
+        .db
+        .update_user(&user_id, update_request.name, update_request.email)
+        .await?
+    {
+        UpdateUserOutcome::NotFound => return Err(ApiError::NotFound),
+        UpdateUserOutcome::AlreadyDeleted => {
+            return Err(ApiError::Conflict(
+                "User was soft-deleted and can no longer be modified".to_string(),
+            ))
+        }
+        UpdateUserOutcome::Updated(user) => user,
+    };
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&user)?);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// DELETE - DELETE /users/:id (PROTEGIDA, requer escopo users:delete)
+async fn delete_user(req: Request<AppState>) -> ApiResult {
+    let user_id: String = req.param("id")?.to_string();
+
+    match req.state().db.delete_user(&user_id).await? {
+        DeleteUserOutcome::NotFound => return Err(ApiError::NotFound),
+        DeleteUserOutcome::AlreadyDeleted => {
+            return Err(ApiError::Conflict(
+                "User was soft-deleted and can no longer be removed".to_string(),
+            ))
         }
+        DeleteUserOutcome::Removed => {}
     }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "User deleted successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
 }
 
-// DELETE - DELETE /users/:id (PROTEGIDA)
-async fn delete_user(req: Request<AppState>) -> Result {
-    // Verificar autenticação
-    if extract_token_claims(&req).await.is_none() {
-        let mut response = Response::new(StatusCode::Unauthorized);
-        response.set_body(r#"{"error": "Authentication required for this operation"}"#);
-        response.set_content_type("application/json");
-        return Ok(response);
+// ROTAS DE GESTÃO ADMINISTRATIVA DE ADMINS (PROTEGIDAS, requer escopo admin)
+
+// POST /admin/admins/:username/block - Bloquear um admin: `login` passa a
+// recusar com 403 mesmo com credenciais corretas, e qualquer token já
+// emitido para ele é rejeitado por `extract_token_claims_for_issuer`.
+async fn block_admin(req: Request<AppState>) -> ApiResult {
+    let username: String = req.param("username")?.to_string();
+    let claims = req.ext::<Claims>().cloned().ok_or(ApiError::MissingToken)?;
+    if claims.username == username {
+        return Err(ApiError::Forbidden(
+            "Cannot block your own admin account".to_string(),
+        ));
     }
-    
+
+    let found = req
+        .state()
+        .db
+        .set_admin_blocked(&username, true, Some(Utc::now().to_rfc3339()))
+        .await?;
+    if !found {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "Admin blocked successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// POST /admin/admins/:username/unblock - Reverter o bloqueio de um admin
+async fn unblock_admin(req: Request<AppState>) -> ApiResult {
+    let username: String = req.param("username")?.to_string();
+
+    if !req.state().db.set_admin_blocked(&username, false, None).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "Admin unblocked successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// ROTAS DE GESTÃO ADMINISTRATIVA DE USUÁRIOS (PROTEGIDAS, requer escopo admin)
+
+// POST /admin/users/:id/block - Bloquear um usuário (oculto de get_all_users)
+async fn block_user(req: Request<AppState>) -> ApiResult {
     let user_id: String = req.param("id")?.to_string();
-    
-    let mut users = req.state().users.lock().await;
-    
-    match users.remove(&user_id) {
-        Some(_) => {
-            let mut response = Response::new(StatusCode::Ok);
-            response.set_body(r#"{"message": "User deleted successfully"}"#);
-            response.set_content_type("application/json");
-            Ok(response)
-        }
-        None => {
-            let mut response = Response::new(StatusCode::NotFound);
-            response.set_body(r#"{"error": "User not found"}"#);
-            response.set_content_type("application/json");
-            Ok(response)
-        }
+
+    let found = req
+        .state()
+        .db
+        .set_user_blocked(&user_id, true, Some(Utc::now().to_rfc3339()))
+        .await?;
+    if !found {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "User blocked successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// POST /admin/users/:id/unblock - Reverter o bloqueio de um usuário
+async fn unblock_user(req: Request<AppState>) -> ApiResult {
+    let user_id: String = req.param("id")?.to_string();
+
+    if !req.state().db.set_user_blocked(&user_id, false, None).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "User unblocked successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
+}
+
+// DELETE /admin/users/:id - Soft-delete (tombstone): oculta de get_all_users
+// mas preserva a linha para auditoria, ao contrário do DELETE /users/:id
+// legado, que remove fisicamente. Usa o flag `deleted`, separado de
+// `blocked`, porque não há (nem deve haver) um "undelete" equivalente ao
+// /unblock — diferente de um bloqueio administrativo, revertê-lo não é uma
+// operação suportada.
+async fn soft_delete_user(req: Request<AppState>) -> ApiResult {
+    let user_id: String = req.param("id")?.to_string();
+
+    let found = req
+        .state()
+        .db
+        .set_user_deleted(&user_id, true, Some(Utc::now().to_rfc3339()))
+        .await?;
+    if !found {
+        return Err(ApiError::NotFound);
     }
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(r#"{"message": "User soft-deleted successfully"}"#);
+    response.set_content_type("application/json");
+    Ok(response)
 }
 
 // ROTAS PÚBLICAS (não necessitam autenticação)
 
-// READ ALL - GET /users (PÚBLICA)
-async fn get_all_users(req: Request<AppState>) -> Result {
-    let users = req.state().users.lock().await;
-    let users_list: Vec<User> = users.values().cloned().collect();
-    
+#[derive(Debug, Deserialize)]
+struct UsersQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+// READ ALL - GET /users?limit=&offset= (PÚBLICA)
+async fn get_all_users(req: Request<AppState>) -> ApiResult {
+    let pagination: UsersQuery = req.query()?;
+    let limit = pagination
+        .limit
+        .unwrap_or(DEFAULT_USERS_PAGE_LIMIT)
+        .clamp(1, MAX_USERS_PAGE_LIMIT);
+    let offset = pagination.offset.unwrap_or(0).max(0);
+
+    let users_list = req.state().db.list_users(limit, offset).await?;
+
     let mut response = Response::new(StatusCode::Ok);
     response.set_body(serde_json::to_string(&users_list)?);
     response.set_content_type("application/json");
@@ -277,24 +810,20 @@ async fn get_all_users(req: Request<AppState>) -> Result {
 }
 
 // READ ONE - GET /users/:id (PÚBLICA)
-async fn get_user_by_id(req: Request<AppState>) -> Result {
+async fn get_user_by_id(req: Request<AppState>) -> ApiResult {
     let user_id: String = req.param("id")?.to_string();
-    let users = req.state().users.lock().await;
-    
-    match users.get(&user_id) {
-        Some(user) => {
-            let mut response = Response::new(StatusCode::Ok);
-            response.set_body(serde_json::to_string(user)?);
-            response.set_content_type("application/json");
-            Ok(response)
-        }
-        None => {
-            let mut response = Response::new(StatusCode::NotFound);
-            response.set_body(r#"{"error": "User not found"}"#);
-            response.set_content_type("application/json");
-            Ok(response)
-        }
-    }
+
+    let user = req
+        .state()
+        .db
+        .get_user(&user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let mut response = Response::new(StatusCode::Ok);
+    response.set_body(serde_json::to_string(&user)?);
+    response.set_content_type("application/json");
+    Ok(response)
 }
 
 // Função principal
@@ -303,24 +832,72 @@ async fn main() -> tide::Result<()> {
     // Carregar variáveis de ambiente se existir arquivo .env
     dotenv::dotenv().ok();
     
-    let state = AppState::new();
+    let state = AppState::new()
+        .await
+        .map_err(|err| tide::Error::from_str(500, format!("Failed to initialize database: {}", err)))?;
     let mut app = tide::with_state(state);
     
     // Middleware para logs
     app.with(tide::log::LogMiddleware::new());
     
     // ROTAS DE AUTENTICAÇÃO
-    app.at("/auth/login").post(login);
-    app.at("/auth/verify").get(verify_token);
-    
+    app.at("/auth/login").post(adapt(login));
+    app.at("/auth/verify").get(adapt(verify_token));
+    app.at("/auth/refresh").post(adapt(refresh_token));
+    app.at("/auth/logout").post(adapt(logout));
+    app.at("/auth/2fa/enroll")
+        .with(AuthMiddleware)
+        .post(adapt(enroll_totp));
+    app.at("/auth/2fa/confirm")
+        .with(AuthMiddleware)
+        .post(adapt(confirm_totp));
+    app.at("/auth/change-password")
+        .with(AuthMiddleware)
+        .post(adapt(change_password));
+    app.at("/auth/delete-token")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_USERS_DELETE))
+        .post(adapt(issue_delete_token));
+
     // ROTAS PROTEGIDAS (necessitam token JWT)
-    app.at("/users").post(create_user);           // CREATE (protegida)
-    app.at("/users/:id").put(update_user);        // UPDATE (protegida)
-    app.at("/users/:id").delete(delete_user);     // DELETE (protegida)
-    
+    app.at("/users")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_USERS_WRITE))
+        .post(adapt(create_user)); // CREATE (protegida, escopo users:write)
+    app.at("/users/:id")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_USERS_WRITE))
+        .put(adapt(update_user)); // UPDATE (protegida, escopo users:write)
+    app.at("/users/:id")
+        .with(DeleteAuthMiddleware)
+        .with(require_scope(SCOPE_USERS_DELETE))
+        .delete(adapt(delete_user)); // DELETE (protegida, token de exclusão via /auth/delete-token)
+
+    // ROTAS DE GESTÃO ADMINISTRATIVA (protegidas, escopo admin)
+    app.at("/admin/admins/:username/block")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_ADMIN))
+        .post(adapt(block_admin));
+    app.at("/admin/admins/:username/unblock")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_ADMIN))
+        .post(adapt(unblock_admin));
+    app.at("/admin/users/:id/block")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_ADMIN))
+        .post(adapt(block_user));
+    app.at("/admin/users/:id/unblock")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_ADMIN))
+        .post(adapt(unblock_user));
+    app.at("/admin/users/:id")
+        .with(AuthMiddleware)
+        .with(require_scope(SCOPE_ADMIN))
+        .delete(adapt(soft_delete_user));
+
     // ROTAS PÚBLICAS (sem autenticação)
-    app.at("/users").get(get_all_users);          // READ ALL (pública)
-    app.at("/users/:id").get(get_user_by_id);     // READ ONE (pública)
+    app.at("/users").get(adapt(get_all_users));   // READ ALL (pública)
+    app.at("/users/:id").get(adapt(get_user_by_id)); // READ ONE (pública)
     
     // Health check
     app.at("/health").get(|_| async move {
@@ -334,15 +911,27 @@ async fn main() -> tide::Result<()> {
     println!("🚀 Servidor rodando em {}", address);
     println!("🔐 Autenticação JWT habilitada");
     println!("📖 Documentação das rotas:");
-    println!("  POST   /auth/login     - Fazer login (receber token)");
+    println!("  POST   /auth/login     - Fazer login (receber access + refresh token)");
     println!("  GET    /auth/verify    - Verificar token");
+    println!("  POST   /auth/refresh   - Trocar refresh token por um novo par");
+    println!("  POST   /auth/logout    - Revogar um refresh token");
+    println!("  POST   /auth/2fa/enroll  - Gerar segredo TOTP pendente (auth)");
+    println!("  POST   /auth/2fa/confirm - Confirmar e ativar o TOTP (auth)");
+    println!("  POST   /auth/change-password - Trocar a própria senha (auth)");
+    println!("  POST   /auth/delete-token - Trocar um token de login por um token de exclusão (auth, escopo users:delete)");
     println!("  📖 ROTAS PÚBLICAS:");
-    println!("  GET    /users          - Listar usuários (sem auth)");
+    println!("  GET    /users          - Listar usuários, paginado (?limit=&offset=) (sem auth)");
     println!("  GET    /users/:id      - Buscar usuário (sem auth)");
     println!("  🔒 ROTAS PROTEGIDAS (necessitam Bearer token):");
     println!("  POST   /users          - Criar usuário");
     println!("  PUT    /users/:id      - Atualizar usuário");
-    println!("  DELETE /users/:id      - Deletar usuário");
+    println!("  DELETE /users/:id      - Deletar usuário (remoção física, requer token de exclusão)");
+    println!("  🔒 ROTAS DE GESTÃO ADMINISTRATIVA (escopo admin):");
+    println!("  POST   /admin/admins/:username/block   - Bloquear admin");
+    println!("  POST   /admin/admins/:username/unblock - Desbloquear admin");
+    println!("  POST   /admin/users/:id/block   - Bloquear usuário");
+    println!("  POST   /admin/users/:id/unblock - Desbloquear usuário");
+    println!("  DELETE /admin/users/:id         - Soft-delete (tombstone, preservado p/ auditoria)");
     println!("  GET    /health         - Health check");
     println!("");
     println!("👤 Admin padrão: username=admin, password=admin123");
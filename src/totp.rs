@@ -0,0 +1,84 @@
+// TOTP (RFC 6238) de segundo fator para login de admin.
+//
+// Implementação mínima de HOTP/TOTP sobre HMAC-SHA1: nenhuma dependência de
+// um crate de TOTP pronto, só o bastante para gerar/validar o código de 6
+// dígitos usado por apps como Google Authenticator.
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+type HmacSha1 = Hmac<Sha1>;
+
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_SKEW_STEPS: i64 = 1;
+
+/// Gera um segredo aleatório de 20 bytes (160 bits), codificado em base32
+/// sem padding, pronto para ser inserido num app autenticador.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base32::encode(base32::Alphabet::RFC4648 { padding: false }, &bytes)
+}
+
+/// Monta a URI `otpauth://` usada para preencher o QR code de provisionamento.
+pub fn provisioning_uri(secret_base32: &str, username: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{username}?secret={secret}&issuer={issuer}&digits={digits}&period={period}",
+        issuer = issuer,
+        username = username,
+        secret = secret_base32,
+        digits = TOTP_DIGITS,
+        period = TOTP_STEP_SECONDS,
+    )
+}
+
+// HOTP(key, counter) truncado para TOTP_DIGITS dígitos, conforme RFC 4226/6238.
+fn code_at_counter(secret_base32: &str, counter: u64) -> Option<u32> {
+    let key = base32::decode(base32::Alphabet::RFC4648 { padding: false }, secret_base32)?;
+    let mut mac = HmacSha1::new_from_slice(&key).ok()?;
+    mac.update(&counter.to_be_bytes());
+    let hmac_result = mac.finalize().into_bytes();
+
+    let offset = (hmac_result[hmac_result.len() - 1] & 0x0f) as usize;
+    let truncated = ((hmac_result[offset] as u32 & 0x7f) << 24)
+        | ((hmac_result[offset + 1] as u32) << 16)
+        | ((hmac_result[offset + 2] as u32) << 8)
+        | (hmac_result[offset + 3] as u32);
+
+    Some(truncated % 10u32.pow(TOTP_DIGITS))
+}
+
+/// Valida `code` contra o passo de tempo atual (derivado de `unix_time`),
+/// tolerando uma janela de +-1 passo (30s) para compensar relógios
+/// dessincronizados entre servidor e dispositivo.
+pub fn verify_code(secret_base32: &str, code: &str, unix_time: u64) -> bool {
+    if code.len() != TOTP_DIGITS as usize {
+        return false;
+    }
+    let counter = (unix_time / TOTP_STEP_SECONDS) as i64;
+
+    for skew in -TOTP_SKEW_STEPS..=TOTP_SKEW_STEPS {
+        let step = counter + skew;
+        if step < 0 {
+            continue;
+        }
+        if let Some(expected) = code_at_counter(secret_base32, step as u64) {
+            let expected = format!("{:0width$}", expected, width = TOTP_DIGITS as usize);
+            if constant_time_eq(expected.as_bytes(), code.as_bytes()) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Compara os dois códigos em tempo constante (sem retorno antecipado no
+// primeiro byte diferente), para não vazar, via timing, quantos dígitos
+// iniciais do código apresentado já batem com o esperado.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
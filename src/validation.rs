@@ -0,0 +1,260 @@
+// Validação de entrada para cadastro de usuários e troca de senha de admin.
+//
+// Dois verificadores leves, sem dependências externas pesadas: sintaxe de
+// e-mail + bloqueio de domínios descartáveis (inspirado no `mailchecker`), e
+// uma estimativa de força de senha no estilo `zxcvbn` (dicionário pequeno +
+// multiplicadores, sem a otimização de segmentação completa do original).
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// Amostra de domínios de e-mail descartável/temporário. Uma lista de
+// produção viria de um arquivo gerado (como faz o `mailchecker`); aqui
+// mantemos só os mais comuns para cobrir o caso de uso do crate.
+const DISPOSABLE_EMAIL_DOMAINS: &[&str] = &[
+    "mailinator.com",
+    "guerrillamail.com",
+    "10minutemail.com",
+    "tempmail.com",
+    "yopmail.com",
+    "trashmail.com",
+    "throwawaymail.com",
+    "fakeinbox.com",
+];
+
+pub fn validate_email(field: &str, email: &str) -> Result<(), FieldError> {
+    let err = |message: &str| FieldError {
+        field: field.to_string(),
+        message: message.to_string(),
+    };
+
+    if email.trim() != email || email.is_empty() {
+        return Err(err("email must not be blank or padded with whitespace"));
+    }
+
+    let mut parts = email.splitn(2, '@');
+    let (local, domain) = match (parts.next(), parts.next()) {
+        (Some(local), Some(domain)) if !local.is_empty() && !domain.is_empty() => (local, domain),
+        _ => return Err(err("email must contain exactly one '@' with content on both sides")),
+    };
+
+    if email.matches('@').count() != 1 || email.contains(char::is_whitespace) {
+        return Err(err("email syntax is invalid"));
+    }
+
+    if !domain.contains('.') || domain.starts_with('.') || domain.ends_with('.') {
+        return Err(err("email domain must contain a valid dot-separated host"));
+    }
+
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err(err("email local part is malformed"));
+    }
+
+    if DISPOSABLE_EMAIL_DOMAINS.contains(&domain.to_lowercase().as_str()) {
+        return Err(err("disposable email domains are not allowed"));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordStrength {
+    pub score: u8, // 0 (trivial) .. 4 (very strong)
+    pub guesses: f64,
+    pub feedback: Vec<String>,
+}
+
+// Pequeno dicionário de senhas/sequências comuns, ordenado por popularidade
+// (a posição determina o "rank", usado como estimativa de tentativas).
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "123456", "12345678", "qwerty", "admin", "admin123", "letmein",
+    "welcome", "monkey", "dragon", "football", "iloveyou", "master", "sunshine",
+    "princess", "123123", "abc123", "password1", "passw0rd", "login",
+];
+
+const KEYBOARD_RUNS: &[&str] = &["qwerty", "qwertyuiop", "asdfgh", "asdfghjkl", "zxcvbn", "1qaz2wsx"];
+
+fn normalize_leet(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            '$' => 's',
+            '@' => 'a',
+            other => other,
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn is_sequential_run(segment: &str, min_len: usize) -> bool {
+    if segment.len() < min_len {
+        return false;
+    }
+    let bytes: Vec<u8> = segment.to_lowercase().into_bytes();
+    let ascending = bytes.windows(2).all(|w| w[1] == w[0] + 1);
+    let descending = bytes.windows(2).all(|w| w[0] == w[1] + 1);
+    ascending || descending
+}
+
+fn charset_size(segment: &str) -> f64 {
+    let has_lower = segment.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = segment.chars().any(|c| c.is_ascii_uppercase());
+    let has_digit = segment.chars().any(|c| c.is_ascii_digit());
+    let has_symbol = segment.chars().any(|c| !c.is_ascii_alphanumeric());
+
+    let mut size = 0.0;
+    if has_lower {
+        size += 26.0;
+    }
+    if has_upper {
+        size += 26.0;
+    }
+    if has_digit {
+        size += 10.0;
+    }
+    if has_symbol {
+        size += 33.0;
+    }
+    size.max(10.0)
+}
+
+// Estimativa de tentativas de força-bruta para um segmento não reconhecido
+// pelo dicionário, assumindo em média metade do espaço de busca.
+fn bruteforce_guesses(segment: &str) -> f64 {
+    (charset_size(segment).powi(segment.chars().count() as i32) / 2.0).max(1.0)
+}
+
+// Varre a senha da esquerda para a direita combinando, de forma gulosa, o
+// maior segmento reconhecido em cada posição (dicionário ou sequência de
+// teclado/numérica); o restante é tratado como força bruta. As contagens de
+// tentativas de cada segmento são combinadas multiplicativamente, que é a
+// aproximação usada pelo zxcvbn para a "menor decomposição de tentativas".
+fn estimate_guesses(password: &str) -> f64 {
+    let len = password.chars().count();
+    if len == 0 {
+        return 1.0;
+    }
+
+    let mut total_guesses = 1.0;
+    let mut pos = 0;
+    let chars: Vec<char> = password.chars().collect();
+
+    while pos < chars.len() {
+        let remaining: String = chars[pos..].iter().collect();
+        // Recalculado a cada posição (em vez de fatiar um `to_lowercase()`
+        // feito uma única vez sobre a senha inteira) porque certos
+        // caracteres, ao serem colocados em minúsculas, expandem para mais
+        // de um char (ex.: `İ` -> `"i̇"`), o que quebraria qualquer offset de
+        // byte calculado a partir da string original.
+        let remaining_lower = remaining.to_lowercase();
+        let remaining_normalized = normalize_leet(&remaining);
+
+        let dict_match = COMMON_PASSWORDS
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| {
+                remaining_lower.starts_with(*word) || remaining_normalized.starts_with(*word)
+            })
+            .max_by_key(|(_, word)| word.len());
+
+        if let Some((rank, word)) = dict_match {
+            // Dicionário costuma ter leet-speak e capitalização como
+            // variantes baratas: multiplica o rank por um pequeno fator.
+            let has_upper = chars[pos..pos + word.len().min(remaining.len())]
+                .iter()
+                .any(|c| c.is_ascii_uppercase());
+            let has_leet = remaining_lower.starts_with(*word) != remaining.starts_with(*word);
+            let multiplier = if has_upper { 4.0 } else { 1.0 } * if has_leet { 4.0 } else { 1.0 };
+            total_guesses *= ((rank + 1) as f64) * multiplier;
+            pos += word.len();
+            continue;
+        }
+
+        let keyboard_match = KEYBOARD_RUNS
+            .iter()
+            .filter(|run| remaining_lower.starts_with(**run))
+            .max_by_key(|run| run.len());
+
+        if let Some(run) = keyboard_match {
+            total_guesses *= 10.0;
+            pos += run.len();
+            continue;
+        }
+
+        // Sequência numérica/alfabética (ex.: "1234", "abcd") de pelo menos 3 chars.
+        let mut run_len = 1;
+        while run_len < remaining.chars().count()
+            && is_sequential_run(&remaining.chars().take(run_len + 1).collect::<String>(), 2)
+        {
+            run_len += 1;
+        }
+        if run_len >= 3 {
+            total_guesses *= 10.0;
+            pos += run_len;
+            continue;
+        }
+
+        // Nenhum padrão reconhecido: consome um caractere como força bruta.
+        total_guesses *= bruteforce_guesses(&remaining.chars().take(1).collect::<String>());
+        pos += 1;
+    }
+
+    total_guesses.max(1.0)
+}
+
+fn guesses_to_score(guesses: f64) -> u8 {
+    let log10 = guesses.max(1.0).log10();
+    match log10 {
+        l if l < 3.0 => 0,
+        l if l < 6.0 => 1,
+        l if l < 8.0 => 2,
+        l if l < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+pub fn estimate_password_strength(password: &str) -> PasswordStrength {
+    let guesses = estimate_guesses(password);
+    let score = guesses_to_score(guesses);
+
+    let mut feedback = Vec::new();
+    if password.len() < 8 {
+        feedback.push("Use at least 8 characters.".to_string());
+    }
+    if score < 2 {
+        feedback.push("Avoid common words, keyboard patterns, and simple substitutions.".to_string());
+    }
+    if feedback.is_empty() {
+        feedback.push("Looks good.".to_string());
+    }
+
+    PasswordStrength {
+        score,
+        guesses,
+        feedback,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Regressão: `İ` (U+0130) vira `"i̇"` (2 chars) ao passar por
+    // `to_lowercase()`, o que antes quebrava o cálculo de offsets de byte
+    // reaproveitados entre a senha original e sua versão em minúsculas.
+    #[test]
+    fn handles_case_expanding_unicode_without_panicking() {
+        let strength = estimate_password_strength("İxyz5678");
+        assert!(strength.score <= 4);
+    }
+}